@@ -0,0 +1,27 @@
+// Command-line entry points for the binary: serving the app, running
+// migrations standalone, and checking DB connectivity from a deploy pipeline.
+use clap::{Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+#[command(name = "backend", about = "Rust backend service and maintenance CLI")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Start the HTTP server (default when no subcommand is given)
+    Serve {
+        /// Override the resolved port; wins over PORT and the compiled default
+        #[arg(long)]
+        port: Option<u16>,
+        /// Override the resolved bind address; wins over SERVER_ADDR and the compiled default
+        #[arg(long)]
+        host: Option<String>,
+    },
+    /// Run the embedded migrations against the configured database and exit
+    Migrate,
+    /// Attempt a database connection and report status; non-zero exit on failure
+    CheckDb,
+}
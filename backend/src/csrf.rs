@@ -0,0 +1,252 @@
+// Double-submit-cookie CSRF protection. Safe requests (GET/HEAD/OPTIONS) get a
+// fresh CSPRNG token in a readable cookie; unsafe requests must echo that token
+// back in a header, or they're rejected with 403. Paths on the allowlist (e.g.
+// health checks) skip the check entirely.
+use actix_web::body::EitherBody;
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::Method;
+use actix_web::{Error, HttpResponse};
+use futures_util::future::{ok, LocalBoxFuture, Ready};
+use rand::RngCore;
+use std::rc::Rc;
+
+const TOKEN_BYTES: usize = 32;
+
+struct CsrfConfig {
+    cookie_name: String,
+    header_name: String,
+    allowlist: Vec<String>,
+}
+
+#[derive(Clone)]
+pub struct Csrf {
+    config: Rc<CsrfConfig>,
+}
+
+impl Csrf {
+    pub fn new(cookie_name: String, header_name: String, allowlist: Vec<String>) -> Self {
+        Csrf {
+            config: Rc::new(CsrfConfig {
+                cookie_name,
+                header_name,
+                allowlist,
+            }),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for Csrf
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = CsrfMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(CsrfMiddleware {
+            service: Rc::new(service),
+            config: self.config.clone(),
+        })
+    }
+}
+
+pub struct CsrfMiddleware<S> {
+    service: Rc<S>,
+    config: Rc<CsrfConfig>,
+}
+
+impl<S, B> Service<ServiceRequest> for CsrfMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let config = self.config.clone();
+
+        if config.allowlist.iter().any(|path| path == req.path()) {
+            let fut = self.service.call(req);
+            return Box::pin(async move { Ok(fut.await?.map_into_left_body()) });
+        }
+
+        if is_safe_method(req.method()) {
+            let token = generate_token();
+            let cookie_name = config.cookie_name.clone();
+            let fut = self.service.call(req);
+            return Box::pin(async move {
+                let mut res = fut.await?.map_into_left_body();
+                let cookie = Cookie::build(cookie_name, token)
+                    .same_site(SameSite::Strict)
+                    .http_only(false)
+                    .path("/")
+                    .finish();
+                let _ = res.response_mut().add_cookie(&cookie);
+                Ok(res)
+            });
+        }
+
+        let cookie_token = req
+            .cookie(&config.cookie_name)
+            .map(|c| c.value().to_string());
+        let header_token = req
+            .headers()
+            .get(config.header_name.as_str())
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.to_string());
+
+        let tokens_match = matches!(
+            (&cookie_token, &header_token),
+            (Some(c), Some(h)) if !c.is_empty() && c == h
+        );
+
+        if !tokens_match {
+            let response = HttpResponse::Forbidden()
+                .json(serde_json::json!({
+                    "status": "error",
+                    "message": "CSRF token missing or invalid"
+                }))
+                .map_into_right_body();
+            return Box::pin(async move { Ok(req.into_response(response)) });
+        }
+
+        let fut = self.service.call(req);
+        Box::pin(async move { Ok(fut.await?.map_into_left_body()) })
+    }
+}
+
+fn is_safe_method(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+fn generate_token() -> String {
+    let mut bytes = [0u8; TOKEN_BYTES];
+    rand::rngs::OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, web, App, HttpResponse};
+
+    fn ok_handler() -> HttpResponse {
+        HttpResponse::Ok().finish()
+    }
+
+    fn test_csrf() -> Csrf {
+        Csrf::new(
+            "csrf_token".to_string(),
+            "X-CSRF-Token".to_string(),
+            vec!["/open".to_string()],
+        )
+    }
+
+    #[test]
+    fn only_get_head_options_are_safe_methods() {
+        assert!(is_safe_method(&Method::GET));
+        assert!(is_safe_method(&Method::HEAD));
+        assert!(is_safe_method(&Method::OPTIONS));
+        assert!(!is_safe_method(&Method::POST));
+        assert!(!is_safe_method(&Method::PUT));
+        assert!(!is_safe_method(&Method::DELETE));
+    }
+
+    #[actix_web::test]
+    async fn safe_request_issues_a_csrf_cookie() {
+        let app = test::init_service(
+            App::new()
+                .wrap(test_csrf())
+                .route("/safe", web::get().to(ok_handler)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/safe").to_request();
+        let res = test::call_service(&app, req).await;
+
+        assert_eq!(res.status(), 200);
+        assert!(res.response().cookies().any(|c| c.name() == "csrf_token"));
+    }
+
+    #[actix_web::test]
+    async fn unsafe_request_without_header_is_rejected() {
+        let app = test::init_service(
+            App::new()
+                .wrap(test_csrf())
+                .route("/safe", web::post().to(ok_handler)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/safe")
+            .cookie(Cookie::new("csrf_token", "a-real-token"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+
+        assert_eq!(res.status(), 403);
+    }
+
+    #[actix_web::test]
+    async fn unsafe_request_with_mismatched_token_is_rejected() {
+        let app = test::init_service(
+            App::new()
+                .wrap(test_csrf())
+                .route("/safe", web::post().to(ok_handler)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/safe")
+            .cookie(Cookie::new("csrf_token", "cookie-value"))
+            .insert_header(("X-CSRF-Token", "header-value"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+
+        assert_eq!(res.status(), 403);
+    }
+
+    #[actix_web::test]
+    async fn unsafe_request_with_matching_token_passes_through() {
+        let app = test::init_service(
+            App::new()
+                .wrap(test_csrf())
+                .route("/safe", web::post().to(ok_handler)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/safe")
+            .cookie(Cookie::new("csrf_token", "matching-token"))
+            .insert_header(("X-CSRF-Token", "matching-token"))
+            .to_request();
+        let res = test::call_service(&app, req).await;
+
+        assert_eq!(res.status(), 200);
+    }
+
+    #[actix_web::test]
+    async fn allowlisted_path_skips_the_check_entirely() {
+        let app = test::init_service(
+            App::new()
+                .wrap(test_csrf())
+                .route("/open", web::post().to(ok_handler)),
+        )
+        .await;
+
+        let req = test::TestRequest::post().uri("/open").to_request();
+        let res = test::call_service(&app, req).await;
+
+        assert_eq!(res.status(), 200);
+    }
+}
@@ -0,0 +1,243 @@
+// Actix middleware that persists request/response metadata to the `request_log`
+// table instead of (or alongside) stdout, batching inserts on a background task
+// so logging never adds a DB round-trip to the request path.
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::Error;
+use futures_util::future::{ok, LocalBoxFuture, Ready};
+use sqlx::PgPool;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+const MAX_PATH_LEN: usize = 512;
+const MAX_HOSTNAME_LEN: usize = 256;
+const CHANNEL_CAPACITY: usize = 1024;
+const FLUSH_BATCH_SIZE: usize = 50;
+const FLUSH_INTERVAL: Duration = Duration::from_millis(500);
+
+struct LogEntry {
+    method: String,
+    path: String,
+    status: i16,
+    latency_ms: i32,
+    remote_addr: Option<String>,
+    hostname: Option<String>,
+}
+
+/// Middleware that ships request metadata to Postgres. Construct with
+/// [`DbLogger::enabled`] when a pool and `DB_LOGGING=true` are available,
+/// otherwise [`DbLogger::disabled`] makes it a no-op so it can always be
+/// `.wrap()`ped onto the app regardless of whether logging is configured.
+#[derive(Clone)]
+pub struct DbLogger {
+    sender: Option<mpsc::Sender<LogEntry>>,
+}
+
+impl DbLogger {
+    pub fn enabled(pool: PgPool) -> Self {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(flush_task(pool, rx));
+        DbLogger { sender: Some(tx) }
+    }
+
+    pub fn disabled() -> Self {
+        DbLogger { sender: None }
+    }
+}
+
+async fn flush_task(pool: PgPool, mut rx: mpsc::Receiver<LogEntry>) {
+    let mut batch = Vec::with_capacity(FLUSH_BATCH_SIZE);
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+    interval.tick().await; // first tick fires immediately
+
+    loop {
+        tokio::select! {
+            entry = rx.recv() => {
+                match entry {
+                    Some(entry) => {
+                        batch.push(entry);
+                        if batch.len() >= FLUSH_BATCH_SIZE {
+                            flush(&pool, &mut batch).await;
+                        }
+                    }
+                    None => {
+                        flush(&pool, &mut batch).await;
+                        break;
+                    }
+                }
+            }
+            _ = interval.tick() => {
+                flush(&pool, &mut batch).await;
+            }
+        }
+    }
+}
+
+async fn flush(pool: &PgPool, batch: &mut Vec<LogEntry>) {
+    if batch.is_empty() {
+        return;
+    }
+
+    let mut query_builder = sqlx::QueryBuilder::new(
+        "INSERT INTO request_log (method, path, status, latency_ms, remote_addr, hostname) ",
+    );
+    let rows = batch.len();
+    query_builder.push_values(batch.drain(..), |mut row, entry| {
+        row.push_bind(entry.method)
+            .push_bind(entry.path)
+            .push_bind(entry.status)
+            .push_bind(entry.latency_ms)
+            .push_bind(entry.remote_addr)
+            .push_bind(entry.hostname);
+    });
+
+    if let Err(e) = query_builder.build().execute(pool).await {
+        eprintln!("⚠️  Failed to flush {} request log row(s): {}", rows, e);
+    }
+}
+
+fn truncate(s: &str, max_len: usize) -> String {
+    if s.len() <= max_len {
+        s.to_string()
+    } else {
+        s.chars().take(max_len).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str) -> LogEntry {
+        LogEntry {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            status: 200,
+            latency_ms: 1,
+            remote_addr: None,
+            hostname: None,
+        }
+    }
+
+    #[test]
+    fn truncate_leaves_short_strings_untouched() {
+        assert_eq!(truncate("short", 10), "short");
+    }
+
+    #[test]
+    fn truncate_leaves_exact_length_strings_untouched() {
+        assert_eq!(truncate("exact", 5), "exact");
+    }
+
+    #[test]
+    fn truncate_shortens_strings_over_the_limit() {
+        assert_eq!(truncate("this is too long", 4), "this");
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn flush_task_flushes_as_soon_as_a_batch_fills_up(pool: PgPool) {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(flush_task(pool.clone(), rx));
+
+        for i in 0..FLUSH_BATCH_SIZE {
+            tx.send(entry(&format!("/size/{}", i))).await.unwrap();
+        }
+
+        // Well under FLUSH_INTERVAL, so only the size trigger could have flushed this.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let (count,): (i64,) = sqlx::query_as("SELECT count(*) FROM request_log")
+            .fetch_one(&pool)
+            .await
+            .expect("failed to count request_log rows");
+        assert_eq!(count, FLUSH_BATCH_SIZE as i64);
+    }
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn flush_task_flushes_a_partial_batch_on_the_interval(pool: PgPool) {
+        let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+        tokio::spawn(flush_task(pool.clone(), rx));
+
+        tx.send(entry("/interval")).await.unwrap();
+
+        // Short of a full batch, so this can only have flushed via the interval tick.
+        tokio::time::sleep(FLUSH_INTERVAL + Duration::from_millis(200)).await;
+
+        let (count,): (i64,) = sqlx::query_as("SELECT count(*) FROM request_log")
+            .fetch_one(&pool)
+            .await
+            .expect("failed to count request_log rows");
+        assert_eq!(count, 1);
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for DbLogger
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = DbLoggerMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(DbLoggerMiddleware {
+            service: Rc::new(service),
+            sender: self.sender.clone(),
+        })
+    }
+}
+
+pub struct DbLoggerMiddleware<S> {
+    service: Rc<S>,
+    sender: Option<mpsc::Sender<LogEntry>>,
+}
+
+impl<S, B> Service<ServiceRequest> for DbLoggerMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let Some(sender) = self.sender.clone() else {
+            let fut = self.service.call(req);
+            return Box::pin(fut);
+        };
+
+        let start = Instant::now();
+        let method = req.method().to_string();
+        let path = truncate(req.path(), MAX_PATH_LEN);
+        let remote_addr = req
+            .connection_info()
+            .realip_remote_addr()
+            .map(|addr| truncate(addr, 64));
+        let hostname = Some(truncate(req.connection_info().host(), MAX_HOSTNAME_LEN));
+
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await?;
+            let entry = LogEntry {
+                method,
+                path,
+                status: res.status().as_u16() as i16,
+                latency_ms: start.elapsed().as_millis() as i32,
+                remote_addr,
+                hostname,
+            };
+            // Drop the entry rather than block request handling if the channel is full.
+            let _ = sender.try_send(entry);
+            Ok(res)
+        })
+    }
+}
@@ -0,0 +1,107 @@
+// Shared wiring between the running binary and the integration test harness,
+// so both build the exact same route table.
+use actix_web::{web, HttpResponse};
+
+pub mod csrf;
+pub mod db_logger;
+pub mod migrations;
+pub mod settings;
+
+// Liveness probe - the process is up. Always 200, never touches the database.
+pub async fn health_live() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({
+        "status": "OK",
+        "service": "test-backend",
+        "message": "Backend is running"
+    }))
+}
+
+// Readiness probe - only returns 200 once the database is actually reachable,
+// so a load balancer doesn't route traffic before the app can serve it.
+pub async fn health_ready(req: actix_web::HttpRequest) -> HttpResponse {
+    let Some(pool) = req.app_data::<web::Data<sqlx::PgPool>>() else {
+        return HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "status": "not ready",
+            "database": "down",
+            "error": "database connection not available"
+        }));
+    };
+
+    let check = tokio::time::timeout(
+        std::time::Duration::from_secs(2),
+        sqlx::query("SELECT 1").execute(pool.get_ref()),
+    )
+    .await;
+
+    match check {
+        Ok(Ok(_)) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "ready",
+            "database": "up"
+        })),
+        Ok(Err(e)) => HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "status": "not ready",
+            "database": "down",
+            "error": e.to_string()
+        })),
+        Err(_) => HttpResponse::ServiceUnavailable().json(serde_json::json!({
+            "status": "not ready",
+            "database": "down",
+            "error": "database health check timed out"
+        })),
+    }
+}
+
+// Hello endpoint
+pub async fn hello() -> HttpResponse {
+    HttpResponse::Ok().json(serde_json::json!({
+        "message": "Hello World from Rust Backend!",
+        "status": "success"
+    }))
+}
+
+// Database test endpoint (with pool)
+pub async fn db_test(pool: web::Data<sqlx::PgPool>) -> HttpResponse {
+    match sqlx::query("SELECT 1 as test")
+        .execute(pool.get_ref())
+        .await
+    {
+        Ok(_) => HttpResponse::Ok().json(serde_json::json!({
+            "status": "success",
+            "message": "Database connection is working",
+            "test": "passed"
+        })),
+        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
+            "status": "error",
+            "message": "Database query failed",
+            "error": e.to_string()
+        })),
+    }
+}
+
+// Database test endpoint (without pool - fallback)
+pub async fn db_test_no_db() -> HttpResponse {
+    HttpResponse::ServiceUnavailable().json(serde_json::json!({
+        "status": "error",
+        "message": "Database connection not available",
+        "error": "Database was not connected during startup"
+    }))
+}
+
+/// Register the app's routes onto `cfg`. Shared by `main` and the integration
+/// test harness so production and tests wire up identically.
+pub fn configure_app(cfg: &mut web::ServiceConfig, pool: Option<sqlx::PgPool>) {
+    cfg.route("/health", web::get().to(health_live)) // legacy alias for /health/live
+        .route("/health/live", web::get().to(health_live))
+        .route("/health/ready", web::get().to(health_ready))
+        .route("/hello", web::get().to(hello));
+
+    match pool {
+        Some(pool) => {
+            cfg.app_data(web::Data::new(pool))
+                .route("/api/db-test", web::get().to(db_test));
+        }
+        None => {
+            cfg.route("/api/db-test", web::get().to(db_test_no_db));
+        }
+    }
+}
@@ -1,172 +1,105 @@
 use actix_cors::Cors;
-use actix_web::{middleware, web, App, HttpResponse, HttpServer};
+use actix_web::{middleware, App, HttpServer};
+use backend::settings::Settings;
+use backend::{configure_app, csrf, db_logger, migrations};
+use clap::Parser;
+use cli::{Cli, Command};
 use dotenvy::dotenv;
 use std::io::Write;
 
+mod cli;
+
 // Database connection function
-async fn connect_db(database_url: &str) -> Result<sqlx::PgPool, sqlx::Error> {
+async fn connect_db(settings: &Settings) -> Result<sqlx::PgPool, sqlx::Error> {
     println!("📊 Connecting to database...");
     println!("📊 Database URL (sanitized): postgresql://***@localhost/***");
+    println!(
+        "📊 Pool config: max_connections={}, min_connections={}, acquire_timeout={}s, idle_timeout={}s",
+        settings.db_max_connections,
+        settings.db_min_connections,
+        settings.db_acquire_timeout_secs,
+        settings.db_idle_timeout_secs
+    );
 
-    sqlx::PgPool::connect(database_url).await
-}
-
-// Health check endpoint
-async fn health_check() -> HttpResponse {
-    HttpResponse::Ok().json(serde_json::json!({
-        "status": "OK",
-        "service": "test-backend",
-        "message": "Backend is running"
-    }))
-}
-
-// Hello endpoint
-async fn hello() -> HttpResponse {
-    HttpResponse::Ok().json(serde_json::json!({
-        "message": "Hello World from Rust Backend!",
-        "status": "success"
-    }))
-}
-
-// Database test endpoint (with pool)
-async fn db_test(pool: web::Data<sqlx::PgPool>) -> HttpResponse {
-    match sqlx::query("SELECT 1 as test")
-        .execute(pool.get_ref())
+    let connect_options: sqlx::postgres::PgConnectOptions = settings.database_url.parse()?;
+
+    sqlx::postgres::PgPoolOptions::new()
+        .max_connections(settings.db_max_connections)
+        .min_connections(settings.db_min_connections)
+        .acquire_timeout(std::time::Duration::from_secs(
+            settings.db_acquire_timeout_secs,
+        ))
+        .idle_timeout(std::time::Duration::from_secs(
+            settings.db_idle_timeout_secs,
+        ))
+        .connect_with(connect_options)
         .await
-    {
-        Ok(_) => HttpResponse::Ok().json(serde_json::json!({
-            "status": "success",
-            "message": "Database connection is working",
-            "test": "passed"
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(serde_json::json!({
-            "status": "error",
-            "message": "Database query failed",
-            "error": e.to_string()
-        })),
-    }
-}
-
-// Database test endpoint (without pool - fallback)
-async fn db_test_no_db() -> HttpResponse {
-    HttpResponse::ServiceUnavailable().json(serde_json::json!({
-        "status": "error",
-        "message": "Database connection not available",
-        "error": "Database was not connected during startup"
-    }))
-}
-
-// URL encoding helper (same as main app)
-fn encode_url_component(s: &str) -> String {
-    url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
 }
 
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    // Enable panic hook for better error logging
-    std::panic::set_hook(Box::new(|panic_info| {
-        eprintln!("PANIC: {:?}", panic_info);
-        std::io::stderr().flush().ok();
-    }));
-
-    // Flush logs immediately
-    std::io::stdout().flush().ok();
-    std::io::stderr().flush().ok();
-
-    println!("🚀 TEST BACKEND: Starting application...");
-    eprintln!("🚀 TEST BACKEND: Starting application...");
-    println!("📋 Process ID: {}", std::process::id());
-    eprintln!("📋 Process ID: {}", std::process::id());
-
-    // Check PORT before dotenv
-    let port_before = std::env::var("PORT");
-    println!("📋 PORT before dotenv: {:?}", port_before);
-    std::io::stdout().flush().ok();
-
-    // Load environment variables
-    match dotenv() {
-        Ok(_) => println!("✅ Environment variables loaded from .env"),
-        Err(_) => println!("⚠️  No .env file found, using system environment variables"),
-    }
-    std::io::stdout().flush().ok();
+// Run the embedded migrations against the configured database and exit.
+async fn run_migrate(settings: &Settings) -> std::io::Result<()> {
+    let pool = connect_db(settings).await.map_err(|e| {
+        eprintln!("❌ Failed to connect to database: {}", e);
+        std::io::Error::new(std::io::ErrorKind::Other, e)
+    })?;
 
-    // Check PORT after dotenv
-    let port: u16 = std::env::var("PORT")
-        .unwrap_or_else(|_| "8080".to_string())
-        .parse()
-        .unwrap_or_else(|_| {
-            eprintln!("❌ Invalid PORT, defaulting to 8080");
-            8080
-        });
-    println!("📋 PORT after dotenv: {}", port);
-    std::io::stdout().flush().ok();
+    migrations::run(&pool).await.map_err(|e| {
+        eprintln!("❌ Migration failed: {}", e);
+        std::io::Error::new(std::io::ErrorKind::Other, e)
+    })?;
 
-    // Construct database URL (same logic as main app)
-    let db_host = std::env::var("DB_HOST").unwrap_or_else(|_| {
-        eprintln!("⚠️  DB_HOST not set, using default");
-        "/tmp".to_string()
-    });
-    let db_name = std::env::var("DB_NAME").unwrap_or_else(|_| {
-        eprintln!("⚠️  DB_NAME not set, using default");
-        "testdb".to_string()
-    });
-    let db_username = std::env::var("DB_USERNAME").unwrap_or_else(|_| {
-        eprintln!("⚠️  DB_USERNAME not set, using default");
-        "postgres".to_string()
-    });
-    let db_password = std::env::var("DB_PASSWORD").unwrap_or_else(|_| {
-        eprintln!("⚠️  DB_PASSWORD not set, using default");
-        "postgres".to_string()
-    });
+    println!("✅ Migrations applied successfully");
+    Ok(())
+}
 
-    println!("📊 Database configuration:");
-    println!(
-        "   DB_HOST: {}",
-        if db_host.contains('/') {
-            "[Unix socket path]"
-        } else {
-            &db_host
+// Attempt a database connection and report status - useful in deploy pipelines.
+async fn run_check_db(settings: &Settings) -> std::io::Result<()> {
+    match tokio::time::timeout(std::time::Duration::from_secs(5), connect_db(settings)).await {
+        Ok(Ok(_pool)) => {
+            println!("✅ Database connection OK");
+            Ok(())
         }
-    );
-    println!("   DB_NAME: {}", db_name);
-    println!("   DB_USERNAME: {}", db_username);
-    std::io::stdout().flush().ok();
-
-    // URL encode credentials (same as main app)
-    let encoded_username = encode_url_component(&db_username);
-    let encoded_password = encode_url_component(&db_password);
-
-    // Construct database URL - same format as main app
-    let database_url = if db_host.starts_with('/') {
-        // Unix socket connection (Cloud SQL style)
-        format!(
-            "postgresql://{}:{}@localhost/{}?host={}",
-            encoded_username, encoded_password, db_name, db_host
-        )
-    } else {
-        // TCP connection
-        let db_port = std::env::var("DB_PORT")
-            .unwrap_or_else(|_| "5432".to_string())
-            .parse::<u16>()
-            .unwrap_or(5432);
-        format!(
-            "postgresql://{}:{}@{}:{}/{}",
-            encoded_username, encoded_password, db_host, db_port, db_name
-        )
-    };
+        Ok(Err(e)) => {
+            eprintln!("❌ Database connection failed: {}", e);
+            std::process::exit(1);
+        }
+        Err(_) => {
+            eprintln!("❌ Database connection timed out after 5 seconds");
+            std::process::exit(1);
+        }
+    }
+}
 
+async fn run_serve(settings: Settings) -> std::io::Result<()> {
     println!("📊 Attempting database connection with 5s timeout...");
     std::io::stdout().flush().ok();
 
     // Attempt database connection with timeout (non-blocking)
     // Allow server to start even if DB connection fails
     let pool_result =
-        tokio::time::timeout(std::time::Duration::from_secs(5), connect_db(&database_url)).await;
+        tokio::time::timeout(std::time::Duration::from_secs(5), connect_db(&settings)).await;
 
     let pool = match pool_result {
         Ok(Ok(pool)) => {
             println!("✅ Database connected successfully");
             std::io::stdout().flush().ok();
+
+            if settings.run_migrations {
+                println!("📦 Running database migrations...");
+                std::io::stdout().flush().ok();
+                if let Err(e) = migrations::run(&pool).await {
+                    eprintln!("❌ Migration failed: {}", e);
+                    eprintln!("❌ Aborting startup rather than serving a broken schema");
+                    std::io::stderr().flush().ok();
+                    return Err(std::io::Error::new(std::io::ErrorKind::Other, e));
+                }
+                println!("✅ Migrations applied successfully");
+                std::io::stdout().flush().ok();
+            } else {
+                println!("⏭️  RUN_MIGRATIONS=false, skipping migrations");
+                std::io::stdout().flush().ok();
+            }
+
             Some(pool)
         }
         Ok(Err(e)) => {
@@ -186,8 +119,8 @@ async fn main() -> std::io::Result<()> {
     };
 
     // Start server - ensure we're ready before binding
-    println!("🌐 Starting HTTP server on 0.0.0.0:{}", port);
-    eprintln!("🌐 Starting HTTP server on 0.0.0.0:{}", port);
+    println!("🌐 Starting HTTP server on {}:{}", settings.server_addr, settings.port);
+    eprintln!("🌐 Starting HTTP server on {}:{}", settings.server_addr, settings.port);
     std::io::stdout().flush().ok();
     std::io::stderr().flush().ok();
 
@@ -197,13 +130,35 @@ async fn main() -> std::io::Result<()> {
     std::io::stdout().flush().ok();
     std::io::stderr().flush().ok();
 
-    // Get frontend origin from environment or default to localhost:3000
-    let frontend_origin =
-        std::env::var("FRONTEND_ORIGIN").unwrap_or_else(|_| "http://localhost:3000".to_string());
-
-    println!("🌐 Frontend origin: {}", frontend_origin);
+    println!("🌐 Frontend origin: {}", settings.frontend_origin);
     std::io::stdout().flush().ok();
 
+    let frontend_origin = settings.frontend_origin.clone();
+    let server_addr = settings.server_addr.clone();
+    let port = settings.port;
+
+    // Db-backed request logging, gated behind DB_LOGGING; falls back to a
+    // no-op when logging is disabled or the pool never connected.
+    let db_logger = match (&pool, settings.db_logging) {
+        (Some(p), true) => db_logger::DbLogger::enabled(p.clone()),
+        _ => db_logger::DbLogger::disabled(),
+    };
+
+    // Routes that stay reachable without a CSRF token.
+    let csrf = csrf::Csrf::new(
+        settings.csrf_cookie_name.clone(),
+        settings.csrf_header_name.clone(),
+        vec![
+            "/health".to_string(),
+            "/health/live".to_string(),
+            "/health/ready".to_string(),
+            "/hello".to_string(),
+        ],
+    );
+    let csrf_header_name =
+        actix_web::http::header::HeaderName::from_bytes(settings.csrf_header_name.as_bytes())
+            .unwrap_or_else(|_| actix_web::http::header::HeaderName::from_static("x-csrf-token"));
+
     // Move pool into the closure properly
     let pool_opt = pool;
     let server = HttpServer::new(move || {
@@ -226,38 +181,31 @@ async fn main() -> std::io::Result<()> {
                 actix_web::http::header::AUTHORIZATION,
                 actix_web::http::header::ACCEPT,
                 actix_web::http::header::CONTENT_TYPE,
+                csrf_header_name.clone(),
             ])
             .supports_credentials()
             .max_age(3600);
 
-        let mut app = App::new()
-            .wrap(cors)
+        let pool_for_config = pool_opt.clone();
+        // actix-web runs the *last*-registered `.wrap()` first, making it the
+        // outermost layer. `cors` must be last so it still runs (and adds its
+        // headers) on responses the csrf middleware short-circuits with a 403.
+        App::new()
+            .wrap(db_logger.clone())
             .wrap(middleware::Logger::default())
-            .route("/health", web::get().to(health_check))
-            .route("/hello", web::get().to(hello));
-
-        // Only add database pool if connection was successful
-        if let Some(ref p) = pool_opt {
-            let pool_clone = p.clone();
-            app = app
-                .app_data(web::Data::new(pool_clone))
-                .route("/api/db-test", web::get().to(db_test));
-        } else {
-            // Add route without pool - will return error message
-            app = app.route("/api/db-test", web::get().to(db_test_no_db));
-        }
-
-        app
+            .wrap(csrf.clone())
+            .wrap(cors)
+            .configure(move |cfg| configure_app(cfg, pool_for_config.clone()))
     });
 
     // Bind to port
-    println!("🔌 Binding to 0.0.0.0:{}...", port);
-    eprintln!("🔌 Binding to 0.0.0.0:{}...", port);
+    println!("🔌 Binding to {}:{}...", server_addr, port);
+    eprintln!("🔌 Binding to {}:{}...", server_addr, port);
     std::io::stdout().flush().ok();
     std::io::stderr().flush().ok();
 
-    let bound_server = server.bind(("0.0.0.0", port)).map_err(|e| {
-        eprintln!("❌ Failed to bind to 0.0.0.0:{}: {}", port, e);
+    let bound_server = server.bind((server_addr.as_str(), port)).map_err(|e| {
+        eprintln!("❌ Failed to bind to {}:{}: {}", server_addr, port, e);
         eprintln!("❌ Error details: {:?}", e);
         std::io::stderr().flush().ok();
         std::io::stdout().flush().ok();
@@ -265,12 +213,12 @@ async fn main() -> std::io::Result<()> {
     })?;
 
     println!(
-        "✅ Successfully bound to 0.0.0.0:{}, starting server...",
-        port
+        "✅ Successfully bound to {}:{}, starting server...",
+        server_addr, port
     );
     eprintln!(
-        "✅ Successfully bound to 0.0.0.0:{}, starting server...",
-        port
+        "✅ Successfully bound to {}:{}, starting server...",
+        server_addr, port
     );
     std::io::stdout().flush().ok();
     std::io::stderr().flush().ok();
@@ -278,3 +226,53 @@ async fn main() -> std::io::Result<()> {
     // Start the server
     bound_server.run().await
 }
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    // Enable panic hook for better error logging
+    std::panic::set_hook(Box::new(|panic_info| {
+        eprintln!("PANIC: {:?}", panic_info);
+        std::io::stderr().flush().ok();
+    }));
+
+    // Flush logs immediately
+    std::io::stdout().flush().ok();
+    std::io::stderr().flush().ok();
+
+    println!("🚀 TEST BACKEND: Starting application...");
+    eprintln!("🚀 TEST BACKEND: Starting application...");
+    println!("📋 Process ID: {}", std::process::id());
+    eprintln!("📋 Process ID: {}", std::process::id());
+
+    let cli = Cli::parse();
+
+    // Load environment variables
+    match dotenv() {
+        Ok(_) => println!("✅ Environment variables loaded from .env"),
+        Err(_) => println!("⚠️  No .env file found, using system environment variables"),
+    }
+    std::io::stdout().flush().ok();
+
+    let mut settings = Settings::from_env();
+    println!("📋 PORT after dotenv: {}", settings.port);
+    std::io::stdout().flush().ok();
+
+    // Flags layer over Settings: CLI args win, env is the fallback, compiled
+    // constants are last (already baked into Settings::from_env).
+    match cli.command.unwrap_or(Command::Serve {
+        port: None,
+        host: None,
+    }) {
+        Command::Serve { port, host } => {
+            if let Some(port) = port {
+                settings.port = port;
+            }
+            if let Some(host) = host {
+                settings.server_addr = host;
+            }
+            run_serve(settings).await
+        }
+        Command::Migrate => run_migrate(&settings).await,
+        Command::CheckDb => run_check_db(&settings).await,
+    }
+}
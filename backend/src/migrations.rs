@@ -0,0 +1,25 @@
+// Embedded schema migrations, applied on startup so deployments get a
+// reproducible schema instead of manual SQL.
+use sqlx::migrate::MigrateError;
+use sqlx::PgPool;
+
+static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
+
+/// Run every pending migration against `pool`.
+pub async fn run(pool: &PgPool) -> Result<(), MigrateError> {
+    MIGRATOR.run(pool).await
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::PgPool;
+
+    #[sqlx::test(migrations = "./migrations")]
+    async fn migrations_apply_cleanly(pool: PgPool) {
+        let row: (i64,) = sqlx::query_as("SELECT count(*) FROM request_log")
+            .fetch_one(&pool)
+            .await
+            .expect("request_log table should exist after migrations run");
+        assert_eq!(row.0, 0);
+    }
+}